@@ -0,0 +1,277 @@
+// std
+use std::sync::Arc;
+// pbrt
+use crate::core::geometry::bnd3_intersect_b;
+use crate::core::geometry::bnd3_union_bnd3;
+use crate::core::geometry::{Bounds3f, Point3f, Ray, Vector3f};
+use crate::core::interaction::SurfaceInteraction;
+use crate::core::light::AreaLight;
+use crate::core::material::Material;
+use crate::core::paramset::ParamSet;
+use crate::core::pbrt::Float;
+use crate::core::primitive::Primitive;
+
+// a voxel is just the list of primitive indices whose world bound
+// overlaps it; most voxels in a typical scene are empty
+#[derive(Default, Clone)]
+struct Voxel {
+    primitives: Vec<usize>,
+}
+
+/// Alternative to `KdTreeAccel` for scenes where geometry is spread
+/// roughly uniformly through space: build cost is O(n) instead of
+/// O(n log n), at the price of degrading to O(n) traversal when
+/// primitives cluster unevenly.
+pub struct UniformGrid {
+    pub primitives: Vec<Arc<dyn Primitive + Sync + Send>>,
+    pub bounds: Bounds3f,
+    n_voxels: [i32; 3],
+    voxels: Vec<Voxel>,
+    width: Vector3f,
+    inv_width: Vector3f,
+}
+
+impl UniformGrid {
+    pub fn new(primitives: Vec<Arc<dyn Primitive + Sync + Send>>) -> Self {
+        let p_len: usize = primitives.len();
+        let mut bounds: Bounds3f = Bounds3f::default();
+        let mut prim_bounds: Vec<Bounds3f> = Vec::with_capacity(p_len);
+        for p in &primitives {
+            let b: Bounds3f = p.world_bound();
+            bounds = bnd3_union_bnd3(&bounds, &b);
+            prim_bounds.push(b);
+        }
+        // choose voxel resolution per axis: n_voxels ~= density * n_prims,
+        // distributed along each axis proportional to the bbox diagonal
+        let delta: Vector3f = bounds.p_max - bounds.p_min;
+        let max_axis = bounds.maximum_extent();
+        let inv_max_width: Float = if delta[max_axis] > 0.0 as Float {
+            1.0 as Float / delta[max_axis]
+        } else {
+            0.0 as Float
+        };
+        let density: Float = 8.0 as Float;
+        let cube_root: Float = (density * p_len as Float).powf(1.0 as Float / 3.0 as Float);
+        let voxels_per_unit_dist: Float = cube_root * inv_max_width;
+        let mut n_voxels: [i32; 3] = [0; 3];
+        for axis in 0..3 {
+            n_voxels[axis] = (delta[axis as u8] * voxels_per_unit_dist)
+                .round()
+                .max(1.0 as Float) as i32;
+            n_voxels[axis] = n_voxels[axis].min(64);
+        }
+        let width: Vector3f = Vector3f {
+            x: delta.x / n_voxels[0] as Float,
+            y: delta.y / n_voxels[1] as Float,
+            z: delta.z / n_voxels[2] as Float,
+        };
+        let inv_width: Vector3f = Vector3f {
+            x: if width.x == 0.0 as Float {
+                0.0 as Float
+            } else {
+                1.0 as Float / width.x
+            },
+            y: if width.y == 0.0 as Float {
+                0.0 as Float
+            } else {
+                1.0 as Float / width.y
+            },
+            z: if width.z == 0.0 as Float {
+                0.0 as Float
+            } else {
+                1.0 as Float / width.z
+            },
+        };
+        let n_total_voxels: usize =
+            (n_voxels[0] * n_voxels[1] * n_voxels[2]).max(0) as usize;
+        let mut voxels: Vec<Voxel> = vec![Voxel::default(); n_total_voxels];
+        // add primitives to every voxel their world bound overlaps
+        for (prim_num, pb) in prim_bounds.iter().enumerate() {
+            let pmin = UniformGrid::pos_to_voxel(&bounds, &inv_width, &n_voxels, &pb.p_min);
+            let pmax = UniformGrid::pos_to_voxel(&bounds, &inv_width, &n_voxels, &pb.p_max);
+            for z in pmin[2]..=pmax[2] {
+                for y in pmin[1]..=pmax[1] {
+                    for x in pmin[0]..=pmax[0] {
+                        let o = UniformGrid::offset(&n_voxels, x, y, z);
+                        voxels[o].primitives.push(prim_num);
+                    }
+                }
+            }
+        }
+        UniformGrid {
+            primitives,
+            bounds,
+            n_voxels,
+            voxels,
+            width,
+            inv_width,
+        }
+    }
+    pub fn create(prims: Vec<Arc<dyn Primitive + Send + Sync>>, _ps: &ParamSet) -> Arc<UniformGrid> {
+        Arc::new(UniformGrid::new(prims))
+    }
+    fn pos_to_voxel(bounds: &Bounds3f, inv_width: &Vector3f, n_voxels: &[i32; 3], p: &Point3f) -> [i32; 3] {
+        let mut v: [i32; 3] = [0; 3];
+        for axis in 0..3 {
+            let delta = p[axis as u8] - bounds.p_min[axis as u8];
+            let vox = (delta * inv_width[axis as u8]) as i32;
+            v[axis] = vox.max(0).min(n_voxels[axis] - 1);
+        }
+        v
+    }
+    fn voxel_to_pos(&self, p: i32, axis: usize) -> Float {
+        self.bounds.p_min[axis as u8] + p as Float * self.width[axis as u8]
+    }
+    fn offset(n_voxels: &[i32; 3], x: i32, y: i32, z: i32) -> usize {
+        (z * n_voxels[1] * n_voxels[0] + y * n_voxels[0] + x) as usize
+    }
+}
+
+impl Primitive for UniformGrid {
+    fn world_bound(&self) -> Bounds3f {
+        self.bounds
+    }
+    fn intersect(&self, ray: &mut Ray) -> Option<SurfaceInteraction> {
+        // check ray against overall grid bounds
+        let t_min: Float;
+        match bnd3_intersect_b(&self.bounds, ray) {
+            Some((t0, _t1)) => {
+                t_min = t0;
+            }
+            None => return None,
+        }
+        let ray_grid_origin = ray.o + ray.d * t_min;
+        // set up 3D DDA for ray
+        let mut pos: [i32; 3] = [0; 3];
+        let mut next_crossing_t: [Float; 3] = [0.0 as Float; 3];
+        let mut delta_t: [Float; 3] = [0.0 as Float; 3];
+        let mut step: [i32; 3] = [0; 3];
+        let mut out: [i32; 3] = [0; 3];
+        for axis in 0..3 {
+            pos[axis] = UniformGrid::pos_to_voxel(
+                &self.bounds,
+                &self.inv_width,
+                &self.n_voxels,
+                &ray_grid_origin,
+            )[axis];
+            if ray.d[axis as u8] >= 0.0 as Float {
+                next_crossing_t[axis] = t_min
+                    + (self.voxel_to_pos(pos[axis] + 1, axis) - ray_grid_origin[axis as u8])
+                        / ray.d[axis as u8];
+                delta_t[axis] = self.width[axis as u8] / ray.d[axis as u8];
+                step[axis] = 1;
+                out[axis] = self.n_voxels[axis];
+            } else {
+                next_crossing_t[axis] = t_min
+                    + (self.voxel_to_pos(pos[axis], axis) - ray_grid_origin[axis as u8])
+                        / ray.d[axis as u8];
+                delta_t[axis] = -self.width[axis as u8] / ray.d[axis as u8];
+                step[axis] = -1;
+                out[axis] = -1;
+            }
+        }
+        // walk ray through voxel grid
+        let mut hit: Option<SurfaceInteraction> = None;
+        loop {
+            let voxel = &self.voxels[UniformGrid::offset(&self.n_voxels, pos[0], pos[1], pos[2])];
+            for &prim_num in &voxel.primitives {
+                if let Some(isect) = self.primitives[prim_num].intersect(ray) {
+                    hit = Some(isect);
+                }
+            }
+            // advance to next voxel along the ray
+            let axis = if next_crossing_t[0] < next_crossing_t[1] {
+                if next_crossing_t[0] < next_crossing_t[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if next_crossing_t[1] < next_crossing_t[2] {
+                1
+            } else {
+                2
+            };
+            if ray.t_max.get() < next_crossing_t[axis] {
+                break;
+            }
+            pos[axis] += step[axis];
+            if pos[axis] == out[axis] {
+                break;
+            }
+            next_crossing_t[axis] += delta_t[axis];
+        }
+        hit
+    }
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        let t_min: Float;
+        match bnd3_intersect_b(&self.bounds, ray) {
+            Some((t0, _t1)) => {
+                t_min = t0;
+            }
+            None => return false,
+        }
+        let ray_grid_origin = ray.o + ray.d * t_min;
+        let mut pos: [i32; 3] = [0; 3];
+        let mut next_crossing_t: [Float; 3] = [0.0 as Float; 3];
+        let mut delta_t: [Float; 3] = [0.0 as Float; 3];
+        let mut step: [i32; 3] = [0; 3];
+        let mut out: [i32; 3] = [0; 3];
+        for axis in 0..3 {
+            pos[axis] = UniformGrid::pos_to_voxel(
+                &self.bounds,
+                &self.inv_width,
+                &self.n_voxels,
+                &ray_grid_origin,
+            )[axis];
+            if ray.d[axis as u8] >= 0.0 as Float {
+                next_crossing_t[axis] = t_min
+                    + (self.voxel_to_pos(pos[axis] + 1, axis) - ray_grid_origin[axis as u8])
+                        / ray.d[axis as u8];
+                delta_t[axis] = self.width[axis as u8] / ray.d[axis as u8];
+                step[axis] = 1;
+                out[axis] = self.n_voxels[axis];
+            } else {
+                next_crossing_t[axis] = t_min
+                    + (self.voxel_to_pos(pos[axis], axis) - ray_grid_origin[axis as u8])
+                        / ray.d[axis as u8];
+                delta_t[axis] = -self.width[axis as u8] / ray.d[axis as u8];
+                step[axis] = -1;
+                out[axis] = -1;
+            }
+        }
+        loop {
+            let voxel = &self.voxels[UniformGrid::offset(&self.n_voxels, pos[0], pos[1], pos[2])];
+            for &prim_num in &voxel.primitives {
+                if self.primitives[prim_num].intersect_p(ray) {
+                    return true;
+                }
+            }
+            let axis = if next_crossing_t[0] < next_crossing_t[1] {
+                if next_crossing_t[0] < next_crossing_t[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if next_crossing_t[1] < next_crossing_t[2] {
+                1
+            } else {
+                2
+            };
+            if ray.t_max.get() < next_crossing_t[axis] {
+                break;
+            }
+            pos[axis] += step[axis];
+            if pos[axis] == out[axis] {
+                break;
+            }
+            next_crossing_t[axis] += delta_t[axis];
+        }
+        false
+    }
+    fn get_material(&self) -> Option<Arc<dyn Material + Send + Sync>> {
+        None
+    }
+    fn get_area_light(&self) -> Option<Arc<dyn AreaLight + Send + Sync>> {
+        None
+    }
+}
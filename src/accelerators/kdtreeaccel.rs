@@ -1,9 +1,14 @@
 // std
 use std;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
 use std::sync::Arc;
 // pbrt
 use crate::core::geometry::bnd3_union_bnd3;
-use crate::core::geometry::{Bounds3f, Ray, Vector3f};
+use crate::core::geometry::bnd3_intersect_b;
+use crate::core::geometry::{Bounds3f, Point3f, Ray, Vector3f};
 use crate::core::interaction::SurfaceInteraction;
 use crate::core::light::AreaLight;
 use crate::core::material::Material;
@@ -11,8 +16,65 @@ use crate::core::paramset::ParamSet;
 use crate::core::pbrt::log_2_int_i32;
 use crate::core::pbrt::Float;
 use crate::core::primitive::Primitive;
+use crate::accelerators::stats::AccelStats;
 
-pub struct KdAccelNode {}
+// bump this whenever the on-disk layout of `KdTreeAccel::write`/`read`
+// changes, so a stale cache file is rejected instead of misread
+const KD_TREE_CACHE_MAGIC: u32 = 0x6b64_7431; // "kdt1"
+
+// KdAccelNode is packed into 8 bytes in pbrt itself (a C++ union of the
+// interior and leaf representations); we keep the two cases in separate
+// fields here and rely on the `flags` word to tell us which one is live.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct KdAccelNode {
+    // interior
+    pub split: Float,
+    // leaf
+    pub one_primitive: i32,
+    pub primitive_indices_offset: i32,
+    // both: low 2 bits are the split axis (0,1,2) or 3 for a leaf; the
+    // upper 30 bits are either the above-child index (interior) or the
+    // number of primitives stored in the leaf.
+    pub flags: i32,
+}
+
+impl KdAccelNode {
+    pub fn init_leaf(&mut self, prim_nums: &[usize], np: usize, primitive_indices: &mut Vec<usize>) {
+        self.flags = 3;
+        self.flags |= (np as i32) << 2;
+        // store primitive ids for leaf node
+        if np == 0 {
+            self.one_primitive = 0;
+        } else if np == 1 {
+            self.one_primitive = prim_nums[0] as i32;
+        } else {
+            self.primitive_indices_offset = primitive_indices.len() as i32;
+            for i in 0..np {
+                primitive_indices.push(prim_nums[i]);
+            }
+        }
+    }
+    pub fn init_interior(&mut self, axis: u8, above_child: i32, split: Float) {
+        self.split = split;
+        self.flags = axis as i32;
+        self.flags |= above_child << 2;
+    }
+    pub fn split_pos(&self) -> Float {
+        self.split
+    }
+    pub fn n_primitives(&self) -> i32 {
+        self.flags >> 2
+    }
+    pub fn split_axis(&self) -> i32 {
+        self.flags & 3
+    }
+    pub fn is_leaf(&self) -> bool {
+        (self.flags & 3) == 3
+    }
+    pub fn above_child(&self) -> i32 {
+        self.flags >> 2
+    }
+}
 
 #[derive(Debug, PartialEq, PartialOrd)]
 pub enum EdgeType {
@@ -53,16 +115,40 @@ impl Default for BoundEdge {
     }
 }
 
+// used while traversing the kd-tree in `intersect`/`intersect_p`
+#[derive(Debug, Default, Copy, Clone)]
+struct KdToDo {
+    node_num: i32,
+    t_min: Float,
+    t_max: Float,
+}
+
 pub struct KdTreeAccel {
     pub isect_cost: i32,
     pub traversal_cost: i32,
     pub max_prims: i32,
     pub empty_bonus: Float,
     pub primitives: Vec<Arc<dyn Primitive + Sync + Send>>,
+    pub primitive_indices: Vec<usize>,
     pub nodes: Vec<KdAccelNode>,
     pub n_alloced_nodes: i32,
     pub next_free_node: i32,
     pub bounds: Bounds3f,
+    // hard cap on recursion depth; build_tree always turns a node into a
+    // leaf once it is reached, regardless of what the SAH cost estimate
+    // says, so pathological inputs (e.g. many large overlapping
+    // primitives) can't recurse forever or overrun the preallocated
+    // `prims1` scratch buffer
+    pub max_depth: i32,
+    // when set, primitives are re-clipped against the current node bounds
+    // before being classified against a candidate split, so a primitive
+    // that merely straddles the node (without actually reaching the far
+    // side of it) does not inflate the edge list with a bound it doesn't
+    // occupy there
+    pub enable_split_clipping: bool,
+    // populated only when `collect_stats` is requested at construction
+    // time; `None` keeps the atomics off the hot path entirely
+    pub stats: Option<AccelStats>,
 }
 
 impl KdTreeAccel {
@@ -73,6 +159,9 @@ impl KdTreeAccel {
         empty_bonus: Float,
         max_prims: i32,
         max_depth: i32,
+        enable_split_clipping: bool,
+        collect_stats: bool,
+        debug_stats: bool,
     ) -> Self {
         let p_len: usize = p.len();
         let mut max_depth: i32 = max_depth;
@@ -99,7 +188,7 @@ impl KdTreeAccel {
         ];
         let mut prims0: Vec<usize> = Vec::with_capacity(p_len);
         let mut prims1: Vec<usize> = Vec::with_capacity((max_depth + 1) as usize * p_len);
-        for i in 0..prims1.len() {
+        for _i in 0..prims1.capacity() {
             prims1.push(0_usize);
         }
         // initialize _prim_nums_ for kd-tree construction
@@ -122,13 +211,19 @@ impl KdTreeAccel {
             max_prims,
             empty_bonus,
             primitives: p,
+            primitive_indices: Vec::new(),
             nodes: Vec::new(),
             n_alloced_nodes,
             next_free_node,
             bounds,
+            max_depth,
+            enable_split_clipping,
+            stats: if collect_stats {
+                Some(AccelStats::new(debug_stats))
+            } else {
+                None
+            },
         };
-        // build_tree(0, bounds, prim_bounds, prim_nums.get(), primitives.size(),
-        //           max_depth, edges, prims0.get(), prims1.get());
         KdTreeAccel::build_tree(
             &mut kd_tree,
             0 as i32,
@@ -140,7 +235,9 @@ impl KdTreeAccel {
             &mut prims0[..],
             &mut prims1[..],
             0, // bad_refines
+            0, // depth
         );
+        kd_tree.flatten();
         kd_tree
     }
     pub fn create(prims: Vec<Arc<dyn Primitive + Send + Sync>>, ps: &ParamSet) -> Arc<KdTreeAccel> {
@@ -149,6 +246,9 @@ impl KdTreeAccel {
         let empty_bonus: Float = ps.find_one_float("emptybonus", 0.5 as Float);
         let max_prims: i32 = ps.find_one_int("maxprims", 1);
         let max_depth: i32 = ps.find_one_int("maxdepth", -1);
+        let enable_split_clipping: bool = ps.find_one_bool("enablesplitclipping", false);
+        let collect_stats: bool = ps.find_one_bool("collectstats", false);
+        let debug_stats: bool = ps.find_one_bool("debugstats", false);
         Arc::new(KdTreeAccel::new(
             prims.clone(),
             isect_cost,
@@ -156,8 +256,25 @@ impl KdTreeAccel {
             empty_bonus,
             max_prims,
             max_depth,
+            enable_split_clipping,
+            collect_stats,
+            debug_stats,
         ))
     }
+    pub fn statistics(&self) -> Option<String> {
+        self.stats
+            .as_ref()
+            .map(|stats| stats.summary(self.primitives.len()))
+    }
+    fn grow_nodes(&mut self) {
+        if self.next_free_node == self.n_alloced_nodes {
+            let n_new_alloc_nodes: i32 = std::cmp::max(2 * self.n_alloced_nodes, 512);
+            while self.nodes.len() < n_new_alloc_nodes as usize {
+                self.nodes.push(KdAccelNode::default());
+            }
+            self.n_alloced_nodes = n_new_alloc_nodes;
+        }
+    }
     pub fn build_tree(
         &mut self,
         node_num: i32,
@@ -169,12 +286,24 @@ impl KdTreeAccel {
         prims0: &mut [usize],
         prims1: &mut [usize],
         bad_refines: i32,
+        depth: u64,
     ) {
         let mut bad_refines: i32 = bad_refines;
         assert_eq!(node_num, self.next_free_node);
-        if self.next_free_node == self.n_alloced_nodes {}
+        self.grow_nodes();
         self.next_free_node += 1;
-        // ...
+        // initialize leaf node if termination criteria met; the depth
+        // check is a hard backstop so a node is always turned into a leaf
+        // once `max_depth` is reached, regardless of what the SAH cost
+        // heuristics below decide, which in turn keeps recursion (and the
+        // `prims1` scratch slice it consumes) bounded
+        if n_primitives <= self.max_prims as usize || depth as i32 >= self.max_depth {
+            self.nodes[node_num as usize].init_leaf(prim_nums, n_primitives, &mut self.primitive_indices);
+            if let Some(ref stats) = self.stats {
+                stats.report_leaf(depth, n_primitives as u64);
+            }
+            return;
+        }
         // choose split axis position for interior node
         let mut best_axis: i32 = -1;
         let mut best_offset: i32 = -1;
@@ -191,7 +320,11 @@ impl KdTreeAccel {
             // initialize edges for _axis_
             for i in 0..n_primitives {
                 let pn: usize = prim_nums[i];
-                let bounds: &Bounds3f = &all_prim_bounds[pn];
+                let bounds: Bounds3f = if self.enable_split_clipping {
+                    self.primitives[pn].clipped_world_bound(node_bounds)
+                } else {
+                    all_prim_bounds[pn]
+                };
                 edges[axis as usize][2 * i] = BoundEdge::new(bounds.p_min[axis], pn, true);
                 edges[axis as usize][2 * i + 1] = BoundEdge::new(bounds.p_max[axis], pn, false);
             }
@@ -203,11 +336,6 @@ impl KdTreeAccel {
                     e0.t.partial_cmp(&e1.t).unwrap()
                 }
             });
-            // for i in 0..n_primitives {
-            //     println!("{:?}", edges[axis as usize][2 * i]);
-            //     println!("{:?}", edges[axis as usize][2 * i + 1]);
-            // }
-
             // compute cost of all splits for _axis_ to find best
             let mut n_below: usize = 0;
             let mut n_above: usize = n_primitives;
@@ -249,6 +377,9 @@ impl KdTreeAccel {
                         best_offset = i as i32;
                     }
                 }
+                if edges[axis as usize][i].edge_type == EdgeType::Start {
+                    n_below += 1;
+                }
             }
             assert!(n_below == n_primitives && n_above == 0);
             // create leaf if no good splits were found
@@ -262,12 +393,18 @@ impl KdTreeAccel {
         }
         if best_cost > old_cost {
             bad_refines += 1;
+            if let Some(ref stats) = self.stats {
+                stats.report_bad_refine();
+            }
         }
         if (best_cost > 4.0 as Float * old_cost && n_primitives < 16)
             || best_axis == -1
             || bad_refines == 3
         {
-            // TODO: nodes[node_num].init_leaf(primNums, n_primitives, &primitiveIndices);
+            self.nodes[node_num as usize].init_leaf(prim_nums, n_primitives, &mut self.primitive_indices);
+            if let Some(ref stats) = self.stats {
+                stats.report_leaf(depth, n_primitives as u64);
+            }
             return;
         }
         // classify primitives with respect to split
@@ -285,20 +422,394 @@ impl KdTreeAccel {
                 n1 += 1;
             }
         }
+        // recursively initialize children nodes
+        let axis: u8 = best_axis as u8;
+        let t_split: Float = edges[best_axis as usize][best_offset as usize].t;
+        let mut bounds0: Bounds3f = *node_bounds;
+        let mut bounds1: Bounds3f = *node_bounds;
+        bounds0.p_max[axis] = t_split;
+        bounds1.p_min[axis] = t_split;
+        if let Some(ref stats) = self.stats {
+            stats.report_interior();
+        }
+        let prim_nums0: Vec<usize> = prims0[0..n0].to_vec();
+        let prim_nums1: Vec<usize> = prims1[0..n1].to_vec();
+        KdTreeAccel::build_tree(
+            self,
+            node_num + 1,
+            &bounds0,
+            all_prim_bounds,
+            &prim_nums0[..],
+            n0,
+            edges,
+            prims0,
+            &mut prims1[n_primitives..],
+            bad_refines,
+            depth + 1,
+        );
+        let above_child: i32 = self.next_free_node;
+        self.nodes[node_num as usize].init_interior(axis, above_child, t_split);
+        KdTreeAccel::build_tree(
+            self,
+            above_child,
+            &bounds1,
+            all_prim_bounds,
+            &prim_nums1[..],
+            n1,
+            edges,
+            prims0,
+            &mut prims1[n_primitives..],
+            bad_refines,
+            depth + 1,
+        );
+    }
+    /// Discards any nodes allocated past the ones `build_tree` actually
+    /// used, so `self.nodes` is exactly the flat, depth-first array that
+    /// `intersect`/`intersect_p` and `write` rely on (below-child is
+    /// always `node_num + 1`; only the above-child index is stored).
+    pub fn flatten(&mut self) {
+        self.nodes.truncate(self.next_free_node as usize);
+        self.n_alloced_nodes = self.next_free_node;
+    }
+    /// Dumps the flattened node array, the primitive index table, the
+    /// bounds, and the build configuration (`isect_cost`, `traversal_cost`,
+    /// `max_prims`, `empty_bonus`, `max_depth`, `enable_split_clipping`,
+    /// and whether stats collection was requested) to a binary blob at
+    /// `path`, so large scenes can skip the O(n log n) rebuild on every
+    /// run. Accumulated statistics counters are not persisted: `read`
+    /// starts a fresh `AccelStats` when `collectstats` was on, it just
+    /// doesn't carry over the original build's counts.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&KD_TREE_CACHE_MAGIC.to_le_bytes())?;
+        writer.write_all(&(self.primitives.len() as u64).to_le_bytes())?;
+        writer.write_all(&(self.nodes.len() as u64).to_le_bytes())?;
+        writer.write_all(&(self.primitive_indices.len() as u64).to_le_bytes())?;
+        writer.write_all(&self.bounds.p_min.x.to_le_bytes())?;
+        writer.write_all(&self.bounds.p_min.y.to_le_bytes())?;
+        writer.write_all(&self.bounds.p_min.z.to_le_bytes())?;
+        writer.write_all(&self.bounds.p_max.x.to_le_bytes())?;
+        writer.write_all(&self.bounds.p_max.y.to_le_bytes())?;
+        writer.write_all(&self.bounds.p_max.z.to_le_bytes())?;
+        writer.write_all(&self.isect_cost.to_le_bytes())?;
+        writer.write_all(&self.traversal_cost.to_le_bytes())?;
+        writer.write_all(&self.max_prims.to_le_bytes())?;
+        writer.write_all(&self.empty_bonus.to_le_bytes())?;
+        writer.write_all(&self.max_depth.to_le_bytes())?;
+        writer.write_all(&[self.enable_split_clipping as u8])?;
+        let (collect_stats, debug_stats) = match &self.stats {
+            Some(stats) => (true, stats.debug.load(std::sync::atomic::Ordering::Relaxed)),
+            None => (false, false),
+        };
+        writer.write_all(&[collect_stats as u8])?;
+        writer.write_all(&[debug_stats as u8])?;
+        for node in &self.nodes {
+            writer.write_all(&node.split.to_le_bytes())?;
+            writer.write_all(&node.one_primitive.to_le_bytes())?;
+            writer.write_all(&node.primitive_indices_offset.to_le_bytes())?;
+            writer.write_all(&node.flags.to_le_bytes())?;
+        }
+        for idx in &self.primitive_indices {
+            writer.write_all(&(*idx as u64).to_le_bytes())?;
+        }
+        writer.flush()
+    }
+    /// Reads a blob written by `write` and reconnects it to `primitives`,
+    /// the already-loaded list of scene primitives in the same order the
+    /// tree was built with. Fails if the primitive count does not match,
+    /// since a stale cache would otherwise silently index the wrong
+    /// geometry. Restores the original build configuration (cost
+    /// parameters, `max_depth`, `enable_split_clipping`, whether stats
+    /// collection was on); `statistics()` is available again if
+    /// `collectstats` was set, though its counters start over rather than
+    /// reflecting the original build.
+    pub fn read(path: &Path, primitives: Vec<Arc<dyn Primitive + Sync + Send>>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let mut u32_buf = [0_u8; 4];
+        let mut u64_buf = [0_u8; 8];
+        let mut f32_buf = [0_u8; 4];
+        reader.read_exact(&mut u32_buf)?;
+        if u32::from_le_bytes(u32_buf) != KD_TREE_CACHE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a KdTreeAccel cache file",
+            ));
+        }
+        reader.read_exact(&mut u64_buf)?;
+        let n_primitives = u64::from_le_bytes(u64_buf) as usize;
+        if n_primitives != primitives.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "primitive count mismatch: cache has {}, scene has {}",
+                    n_primitives,
+                    primitives.len()
+                ),
+            ));
+        }
+        reader.read_exact(&mut u64_buf)?;
+        let n_nodes = u64::from_le_bytes(u64_buf) as usize;
+        reader.read_exact(&mut u64_buf)?;
+        let n_primitive_indices = u64::from_le_bytes(u64_buf) as usize;
+        let mut read_float = |reader: &mut BufReader<File>| -> io::Result<Float> {
+            reader.read_exact(&mut f32_buf)?;
+            Ok(Float::from_le_bytes(f32_buf))
+        };
+        let p_min = Point3f {
+            x: read_float(&mut reader)?,
+            y: read_float(&mut reader)?,
+            z: read_float(&mut reader)?,
+        };
+        let p_max = Point3f {
+            x: read_float(&mut reader)?,
+            y: read_float(&mut reader)?,
+            z: read_float(&mut reader)?,
+        };
+        let bounds = Bounds3f { p_min, p_max };
+        reader.read_exact(&mut u32_buf)?;
+        let isect_cost = i32::from_le_bytes(u32_buf);
+        reader.read_exact(&mut u32_buf)?;
+        let traversal_cost = i32::from_le_bytes(u32_buf);
+        reader.read_exact(&mut u32_buf)?;
+        let max_prims = i32::from_le_bytes(u32_buf);
+        let empty_bonus = read_float(&mut reader)?;
+        reader.read_exact(&mut u32_buf)?;
+        let max_depth = i32::from_le_bytes(u32_buf);
+        let mut bool_buf = [0_u8; 1];
+        reader.read_exact(&mut bool_buf)?;
+        let enable_split_clipping = bool_buf[0] != 0;
+        reader.read_exact(&mut bool_buf)?;
+        let collect_stats = bool_buf[0] != 0;
+        reader.read_exact(&mut bool_buf)?;
+        let debug_stats = bool_buf[0] != 0;
+        let mut nodes: Vec<KdAccelNode> = Vec::with_capacity(n_nodes);
+        for _ in 0..n_nodes {
+            let split = read_float(&mut reader)?;
+            reader.read_exact(&mut u32_buf)?;
+            let one_primitive = i32::from_le_bytes(u32_buf);
+            reader.read_exact(&mut u32_buf)?;
+            let primitive_indices_offset = i32::from_le_bytes(u32_buf);
+            reader.read_exact(&mut u32_buf)?;
+            let flags = i32::from_le_bytes(u32_buf);
+            nodes.push(KdAccelNode {
+                split,
+                one_primitive,
+                primitive_indices_offset,
+                flags,
+            });
+        }
+        let mut primitive_indices: Vec<usize> = Vec::with_capacity(n_primitive_indices);
+        for _ in 0..n_primitive_indices {
+            reader.read_exact(&mut u64_buf)?;
+            primitive_indices.push(u64::from_le_bytes(u64_buf) as usize);
+        }
+        let n_alloced_nodes = nodes.len() as i32;
+        let next_free_node = n_alloced_nodes;
+        Ok(KdTreeAccel {
+            isect_cost,
+            traversal_cost,
+            max_prims,
+            empty_bonus,
+            primitives,
+            primitive_indices,
+            nodes,
+            n_alloced_nodes,
+            next_free_node,
+            bounds,
+            max_depth,
+            enable_split_clipping,
+            stats: if collect_stats {
+                Some(AccelStats::new(debug_stats))
+            } else {
+                None
+            },
+        })
     }
 }
 
 impl Primitive for KdTreeAccel {
     fn world_bound(&self) -> Bounds3f {
-        // WORK
-        Bounds3f::default()
+        self.bounds
     }
     fn intersect(&self, ray: &mut Ray) -> Option<SurfaceInteraction> {
-        // WORK
-        None
+        // compute initial parametric range of ray inside kd-tree extent
+        let (mut t_min, mut t_max): (Float, Float);
+        match bnd3_intersect_b(&self.bounds, ray) {
+            Some((t0, t1)) => {
+                t_min = t0;
+                t_max = t1;
+            }
+            None => return None,
+        }
+        // prepare to traverse kd-tree for ray
+        let inv_dir: Vector3f = Vector3f {
+            x: 1.0 as Float / ray.d.x,
+            y: 1.0 as Float / ray.d.y,
+            z: 1.0 as Float / ray.d.z,
+        };
+        let max_todo: usize = 64;
+        let mut todo: Vec<KdToDo> = vec![KdToDo::default(); max_todo];
+        let mut todo_pos: usize = 0;
+        // traverse kd-tree nodes in order for ray
+        let mut hit: Option<SurfaceInteraction> = None;
+        let mut node_idx: i32 = 0;
+        loop {
+            let node: KdAccelNode = self.nodes[node_idx as usize];
+            if ray.t_max.get() < t_min {
+                break;
+            }
+            if let Some(ref stats) = self.stats {
+                stats.report_node_visited();
+            }
+            if !node.is_leaf() {
+                // process kd-tree interior node
+                let axis: u8 = node.split_axis() as u8;
+                let t_plane: Float = (node.split_pos() - ray.o[axis]) * inv_dir[axis];
+                // get node children pointers for ray
+                let below_first: bool = (ray.o[axis] < node.split_pos())
+                    || (ray.o[axis] == node.split_pos() && ray.d[axis] <= 0.0 as Float);
+                let (first_child, second_child): (i32, i32);
+                if below_first {
+                    first_child = node_idx + 1;
+                    second_child = node.above_child();
+                } else {
+                    first_child = node.above_child();
+                    second_child = node_idx + 1;
+                }
+                // advance to next child node, possibly enqueue other child
+                if t_plane > t_max || t_plane <= 0.0 as Float {
+                    node_idx = first_child;
+                } else if t_plane < t_min {
+                    node_idx = second_child;
+                } else {
+                    // enqueue _second_child_ in todo list
+                    todo[todo_pos].node_num = second_child;
+                    todo[todo_pos].t_min = t_plane;
+                    todo[todo_pos].t_max = t_max;
+                    todo_pos += 1;
+                    node_idx = first_child;
+                    t_max = t_plane;
+                }
+            } else {
+                // check for intersections inside leaf node
+                let n_primitives: i32 = node.n_primitives();
+                if n_primitives == 1 {
+                    let idx: usize = node.one_primitive as usize;
+                    let p = &self.primitives[idx];
+                    if let Some(ref stats) = self.stats {
+                        stats.report_primitive_test();
+                    }
+                    if let Some(isect) = p.intersect(ray) {
+                        hit = Some(isect);
+                    }
+                } else {
+                    for i in 0..n_primitives as usize {
+                        let index: usize =
+                            self.primitive_indices[node.primitive_indices_offset as usize + i];
+                        let p = &self.primitives[index];
+                        if let Some(ref stats) = self.stats {
+                            stats.report_primitive_test();
+                        }
+                        if let Some(isect) = p.intersect(ray) {
+                            hit = Some(isect);
+                        }
+                    }
+                }
+                // grab next node to process from todo list
+                if todo_pos > 0 {
+                    todo_pos -= 1;
+                    node_idx = todo[todo_pos].node_num;
+                    t_min = todo[todo_pos].t_min;
+                    t_max = todo[todo_pos].t_max;
+                } else {
+                    break;
+                }
+            }
+        }
+        hit
     }
     fn intersect_p(&self, ray: &Ray) -> bool {
-        // WORK
+        // compute initial parametric range of ray inside kd-tree extent
+        let (mut t_min, mut t_max): (Float, Float);
+        match bnd3_intersect_b(&self.bounds, ray) {
+            Some((t0, t1)) => {
+                t_min = t0;
+                t_max = t1;
+            }
+            None => return false,
+        }
+        // prepare to traverse kd-tree for ray
+        let inv_dir: Vector3f = Vector3f {
+            x: 1.0 as Float / ray.d.x,
+            y: 1.0 as Float / ray.d.y,
+            z: 1.0 as Float / ray.d.z,
+        };
+        let max_todo: usize = 64;
+        let mut todo: Vec<KdToDo> = vec![KdToDo::default(); max_todo];
+        let mut todo_pos: usize = 0;
+        let mut node_idx: i32 = 0;
+        loop {
+            let node: KdAccelNode = self.nodes[node_idx as usize];
+            if node.is_leaf() {
+                // check for shadow ray intersections inside leaf node
+                let n_primitives: i32 = node.n_primitives();
+                if n_primitives == 1 {
+                    let idx: usize = node.one_primitive as usize;
+                    let p = &self.primitives[idx];
+                    if p.intersect_p(ray) {
+                        return true;
+                    }
+                } else {
+                    for i in 0..n_primitives as usize {
+                        let index: usize =
+                            self.primitive_indices[node.primitive_indices_offset as usize + i];
+                        let p = &self.primitives[index];
+                        if p.intersect_p(ray) {
+                            return true;
+                        }
+                    }
+                }
+                // grab next node to process from todo list
+                if todo_pos > 0 {
+                    todo_pos -= 1;
+                    node_idx = todo[todo_pos].node_num;
+                    t_min = todo[todo_pos].t_min;
+                    t_max = todo[todo_pos].t_max;
+                } else {
+                    break;
+                }
+            } else {
+                // process kd-tree interior node
+                let axis: u8 = node.split_axis() as u8;
+                let t_plane: Float = (node.split_pos() - ray.o[axis]) * inv_dir[axis];
+                // get node children pointers for ray
+                let below_first: bool = (ray.o[axis] < node.split_pos())
+                    || (ray.o[axis] == node.split_pos() && ray.d[axis] <= 0.0 as Float);
+                let (first_child, second_child): (i32, i32);
+                if below_first {
+                    first_child = node_idx + 1;
+                    second_child = node.above_child();
+                } else {
+                    first_child = node.above_child();
+                    second_child = node_idx + 1;
+                }
+                // advance to next child node, possibly enqueue other child
+                if t_plane > t_max || t_plane <= 0.0 as Float {
+                    node_idx = first_child;
+                } else if t_plane < t_min {
+                    node_idx = second_child;
+                } else {
+                    todo[todo_pos].node_num = second_child;
+                    todo[todo_pos].t_min = t_plane;
+                    todo[todo_pos].t_max = t_max;
+                    todo_pos += 1;
+                    node_idx = first_child;
+                    t_max = t_plane;
+                }
+            }
+        }
         false
     }
     fn get_material(&self) -> Option<Arc<dyn Material + Send + Sync>> {
@@ -307,4 +818,91 @@ impl Primitive for KdTreeAccel {
     fn get_area_light(&self) -> Option<Arc<dyn AreaLight + Send + Sync>> {
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockPrimitive {
+        bounds: Bounds3f,
+    }
+
+    impl Primitive for MockPrimitive {
+        fn world_bound(&self) -> Bounds3f {
+            self.bounds
+        }
+        fn intersect(&self, _ray: &mut Ray) -> Option<SurfaceInteraction> {
+            None
+        }
+        fn intersect_p(&self, _ray: &Ray) -> bool {
+            false
+        }
+        fn get_material(&self) -> Option<Arc<dyn Material + Send + Sync>> {
+            None
+        }
+        fn get_area_light(&self) -> Option<Arc<dyn AreaLight + Send + Sync>> {
+            None
+        }
+    }
+
+    fn mock_primitives() -> Vec<Arc<dyn Primitive + Sync + Send>> {
+        let mut prims: Vec<Arc<dyn Primitive + Sync + Send>> = Vec::new();
+        for i in 0..8 {
+            let offset = i as Float;
+            prims.push(Arc::new(MockPrimitive {
+                bounds: Bounds3f {
+                    p_min: Point3f {
+                        x: offset,
+                        y: offset,
+                        z: offset,
+                    },
+                    p_max: Point3f {
+                        x: offset + 1.0 as Float,
+                        y: offset + 1.0 as Float,
+                        z: offset + 1.0 as Float,
+                    },
+                },
+            }));
+        }
+        prims
+    }
+
+    #[test]
+    fn write_read_round_trip_preserves_tree() {
+        let built = KdTreeAccel::new(mock_primitives(), 80, 1, 0.5 as Float, 1, -1, true, true, true);
+        let path = std::env::temp_dir().join("kdtreeaccel_round_trip_test.kdcache");
+        built.write(&path).expect("failed to write kd-tree cache");
+        let read_back =
+            KdTreeAccel::read(&path, mock_primitives()).expect("failed to read kd-tree cache");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(built.isect_cost, read_back.isect_cost);
+        assert_eq!(built.traversal_cost, read_back.traversal_cost);
+        assert_eq!(built.max_prims, read_back.max_prims);
+        assert_eq!(built.empty_bonus, read_back.empty_bonus);
+        assert_eq!(built.max_depth, read_back.max_depth);
+        assert_eq!(built.enable_split_clipping, read_back.enable_split_clipping);
+        assert!(read_back.stats.is_some());
+        assert_eq!(built.nodes.len(), read_back.nodes.len());
+        assert_eq!(built.primitive_indices, read_back.primitive_indices);
+        assert_eq!(built.bounds.p_min.x, read_back.bounds.p_min.x);
+        assert_eq!(built.bounds.p_min.y, read_back.bounds.p_min.y);
+        assert_eq!(built.bounds.p_min.z, read_back.bounds.p_min.z);
+        assert_eq!(built.bounds.p_max.x, read_back.bounds.p_max.x);
+        assert_eq!(built.bounds.p_max.y, read_back.bounds.p_max.y);
+        assert_eq!(built.bounds.p_max.z, read_back.bounds.p_max.z);
+        for (a, b) in built.nodes.iter().zip(read_back.nodes.iter()) {
+            assert_eq!(a.flags, b.flags);
+            assert_eq!(a.one_primitive, b.one_primitive);
+            assert_eq!(a.primitive_indices_offset, b.primitive_indices_offset);
+            if a.is_leaf() {
+                assert_eq!(a.n_primitives(), b.n_primitives());
+            } else {
+                assert_eq!(a.split_pos(), b.split_pos());
+                assert_eq!(a.split_axis(), b.split_axis());
+                assert_eq!(a.above_child(), b.above_child());
+            }
+        }
+    }
+}
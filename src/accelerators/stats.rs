@@ -0,0 +1,91 @@
+// std
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+// pbrt
+use crate::core::pbrt::Float;
+
+/// Opt-in build/traversal counters for `KdTreeAccel` (and future grid or
+/// octree accelerators). All fields are atomic so they can be updated from
+/// the worker threads that drive the parallel render; reading them only
+/// makes sense once rendering has finished.
+#[derive(Debug, Default)]
+pub struct AccelStats {
+    pub n_interior_nodes: AtomicU64,
+    pub n_leaf_nodes: AtomicU64,
+    pub max_depth: AtomicU64,
+    pub sum_depth: AtomicU64,
+    pub n_leaves_for_avg_depth: AtomicU64,
+    pub n_primitive_refs: AtomicU64,
+    pub n_bad_refines: AtomicU64,
+    // per-ray counters; only accumulated when `debug` is set, since they
+    // would otherwise add an atomic increment to the hot traversal loop
+    // of every production render
+    pub debug: AtomicBool,
+    pub nodes_visited: AtomicI64,
+    pub primitive_tests: AtomicI64,
+}
+
+impl AccelStats {
+    pub fn new(debug: bool) -> Self {
+        let stats = AccelStats::default();
+        stats.debug.store(debug, Ordering::Relaxed);
+        stats
+    }
+    pub fn report_leaf(&self, depth: u64, n_primitives: u64) {
+        self.n_leaf_nodes.fetch_add(1, Ordering::Relaxed);
+        self.n_primitive_refs.fetch_add(n_primitives, Ordering::Relaxed);
+        self.sum_depth.fetch_add(depth, Ordering::Relaxed);
+        self.n_leaves_for_avg_depth.fetch_add(1, Ordering::Relaxed);
+        self.max_depth.fetch_max(depth, Ordering::Relaxed);
+    }
+    pub fn report_interior(&self) {
+        self.n_interior_nodes.fetch_add(1, Ordering::Relaxed);
+    }
+    pub fn report_bad_refine(&self) {
+        self.n_bad_refines.fetch_add(1, Ordering::Relaxed);
+    }
+    pub fn report_node_visited(&self) {
+        if self.debug.load(Ordering::Relaxed) {
+            self.nodes_visited.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    pub fn report_primitive_test(&self) {
+        if self.debug.load(Ordering::Relaxed) {
+            self.primitive_tests.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    pub fn average_depth(&self) -> Float {
+        let n_leaves = self.n_leaves_for_avg_depth.load(Ordering::Relaxed);
+        if n_leaves == 0 {
+            0.0 as Float
+        } else {
+            self.sum_depth.load(Ordering::Relaxed) as Float / n_leaves as Float
+        }
+    }
+    /// Ratio of stored primitive references to unique primitives; a value
+    /// well above 1 means the tree is duplicating a lot of primitives
+    /// across leaves.
+    pub fn duplication_factor(&self, n_unique_primitives: usize) -> Float {
+        if n_unique_primitives == 0 {
+            0.0 as Float
+        } else {
+            self.n_primitive_refs.load(Ordering::Relaxed) as Float / n_unique_primitives as Float
+        }
+    }
+    pub fn summary(&self, n_unique_primitives: usize) -> String {
+        format!(
+            "kd-tree stats: {} interior, {} leaf nodes, max depth {}, avg depth {:.2}, \
+             {} primitive refs for {} unique primitives ({:.2}x duplication), {} bad refines\n\
+             intersect totals over all rays: {} nodes visited, {} primitive tests",
+            self.n_interior_nodes.load(Ordering::Relaxed),
+            self.n_leaf_nodes.load(Ordering::Relaxed),
+            self.max_depth.load(Ordering::Relaxed),
+            self.average_depth(),
+            self.n_primitive_refs.load(Ordering::Relaxed),
+            n_unique_primitives,
+            self.duplication_factor(n_unique_primitives),
+            self.n_bad_refines.load(Ordering::Relaxed),
+            self.nodes_visited.load(Ordering::Relaxed),
+            self.primitive_tests.load(Ordering::Relaxed),
+        )
+    }
+}
@@ -0,0 +1,83 @@
+// std
+use std::sync::Arc;
+// pbrt
+use core::camera::{Camera, CameraSample};
+use core::film::Film;
+use core::geometry::{Point2f, Point3f, Ray, Vector3f};
+use core::pbrt::Float;
+use core::sampling::concentric_sample_disk;
+use core::transform::Transform;
+
+/// Orthographic camera: parallel projection, so unlike `PerspectiveCamera`
+/// every ray shares the same direction in camera space before the lens
+/// (and camera-to-world) transforms are applied.
+pub struct OrthographicCamera {
+    pub camera_to_world: Transform,
+    pub raster_to_camera: Transform,
+    pub lens_radius: Float,
+    pub focal_distance: Float,
+    pub film: Arc<Film>,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        camera_to_world: Transform,
+        raster_to_camera: Transform,
+        lens_radius: Float,
+        focal_distance: Float,
+        film: Arc<Film>,
+    ) -> Self {
+        OrthographicCamera {
+            camera_to_world,
+            raster_to_camera,
+            lens_radius,
+            focal_distance,
+            film,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn generate_ray(&self, sample: &CameraSample, ray: &mut Ray) -> Float {
+        // compute raster and camera sample positions
+        let p_film: Point3f = Point3f {
+            x: sample.p_film.x,
+            y: sample.p_film.y,
+            z: 0.0 as Float,
+        };
+        let p_camera: Point3f = self.raster_to_camera.transform_point(&p_film);
+        *ray = Ray {
+            o: p_camera,
+            d: Vector3f {
+                x: 0.0 as Float,
+                y: 0.0 as Float,
+                z: 1.0 as Float,
+            },
+            t_max: std::cell::Cell::new(std::f32::INFINITY),
+            time: sample.time,
+        };
+        // modify ray for depth of field
+        if self.lens_radius > 0.0 as Float {
+            // sample point on lens
+            let p_lens: Point2f = concentric_sample_disk(&sample.p_lens) * self.lens_radius;
+            // compute point on plane of focus
+            let ft: Float = self.focal_distance / ray.d.z;
+            let p_focus: Point3f = ray.o + ray.d * ft;
+            // update ray for effect of lens
+            ray.o = Point3f {
+                x: p_lens.x,
+                y: p_lens.y,
+                z: 0.0 as Float,
+            };
+            ray.d = (p_focus - ray.o).normalize();
+        }
+        *ray = self.camera_to_world.transform_ray(ray);
+        1.0 as Float
+    }
+    fn generate_ray_differential(&self, sample: &CameraSample, ray: &mut Ray) -> Float {
+        self.generate_ray(sample, ray)
+    }
+    fn get_film(&self) -> Arc<Film> {
+        self.film.clone()
+    }
+}
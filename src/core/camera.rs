@@ -8,6 +8,7 @@ use core::pbrt::Float;
 // see camera.h
 
 pub trait Camera {
+    fn generate_ray(&self, sample: &CameraSample, ray: &mut Ray) -> Float;
     fn generate_ray_differential(&self, sample: &CameraSample, ray: &mut Ray) -> Float;
     fn get_film(&self) -> Arc<Film>;
 }
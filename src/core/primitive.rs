@@ -0,0 +1,27 @@
+// std
+use std::sync::Arc;
+// pbrt
+use crate::core::geometry::{Bounds3f, Ray};
+use crate::core::interaction::SurfaceInteraction;
+use crate::core::light::AreaLight;
+use crate::core::material::Material;
+
+// see primitive.h
+
+pub trait Primitive {
+    fn world_bound(&self) -> Bounds3f;
+    fn intersect(&self, ray: &mut Ray) -> Option<SurfaceInteraction>;
+    fn intersect_p(&self, ray: &Ray) -> bool;
+    fn get_material(&self) -> Option<Arc<dyn Material + Send + Sync>>;
+    fn get_area_light(&self) -> Option<Arc<dyn AreaLight + Send + Sync>>;
+    /// Sub-bound of this primitive's world bound, clipped against
+    /// `clip_bounds` (typically the bounds of the kd-tree node currently
+    /// being built). The default performs no clipping at all; shapes that
+    /// can cheaply clip themselves against an axis-aligned box (e.g.
+    /// `Triangle`, via Sutherland-Hodgman polygon clipping) should
+    /// override this with a tighter bound.
+    fn clipped_world_bound(&self, clip_bounds: &Bounds3f) -> Bounds3f {
+        let _ = clip_bounds;
+        self.world_bound()
+    }
+}
@@ -0,0 +1,186 @@
+// std
+use std::sync::Arc;
+// pbrt
+use crate::core::geometry::{bnd3_union_bnd3, Bounds3f, Normal3f, Point2f, Point3f, Ray, Vector3f};
+use crate::core::interaction::SurfaceInteraction;
+use crate::core::light::AreaLight;
+use crate::core::material::Material;
+use crate::core::pbrt::Float;
+use crate::core::primitive::Primitive;
+
+/// A single triangle, given directly by its three world-space vertices.
+/// (pbrt itself shares vertex data across a `TriangleMesh`; this tree
+/// doesn't have that indirection, so each `Triangle` just owns its three
+/// points.)
+pub struct Triangle {
+    pub p0: Point3f,
+    pub p1: Point3f,
+    pub p2: Point3f,
+    pub material: Option<Arc<dyn Material + Send + Sync>>,
+    pub area_light: Option<Arc<dyn AreaLight + Send + Sync>>,
+}
+
+impl Triangle {
+    pub fn new(
+        p0: Point3f,
+        p1: Point3f,
+        p2: Point3f,
+        material: Option<Arc<dyn Material + Send + Sync>>,
+        area_light: Option<Arc<dyn AreaLight + Send + Sync>>,
+    ) -> Self {
+        Triangle {
+            p0,
+            p1,
+            p2,
+            material,
+            area_light,
+        }
+    }
+    /// Sutherland-Hodgman clip of the triangle polygon against a single
+    /// axis-aligned half-space: `coord[axis] <= value` when `keep_less` is
+    /// set (a max-plane), `coord[axis] >= value` otherwise (a min-plane).
+    fn clip_half_space(poly: &[Point3f], axis: u8, value: Float, keep_less: bool) -> Vec<Point3f> {
+        if poly.is_empty() {
+            return Vec::new();
+        }
+        let inside = |p: &Point3f| -> bool {
+            if keep_less {
+                p[axis] <= value
+            } else {
+                p[axis] >= value
+            }
+        };
+        let mut out: Vec<Point3f> = Vec::with_capacity(poly.len() + 1);
+        for i in 0..poly.len() {
+            let cur = poly[i];
+            let prev = poly[(i + poly.len() - 1) % poly.len()];
+            let cur_in = inside(&cur);
+            let prev_in = inside(&prev);
+            if cur_in != prev_in {
+                // edge crosses the plane: interpolate the intersection point
+                let denom = cur[axis] - prev[axis];
+                let t = if denom != 0.0 as Float {
+                    (value - prev[axis]) / denom
+                } else {
+                    0.0 as Float
+                };
+                out.push(Point3f {
+                    x: prev.x + t * (cur.x - prev.x),
+                    y: prev.y + t * (cur.y - prev.y),
+                    z: prev.z + t * (cur.z - prev.z),
+                });
+            }
+            if cur_in {
+                out.push(cur);
+            }
+        }
+        out
+    }
+}
+
+impl Primitive for Triangle {
+    fn world_bound(&self) -> Bounds3f {
+        let mut bounds: Bounds3f = Bounds3f {
+            p_min: self.p0,
+            p_max: self.p0,
+        };
+        bounds = bnd3_union_bnd3(&bounds, &Bounds3f { p_min: self.p1, p_max: self.p1 });
+        bounds = bnd3_union_bnd3(&bounds, &Bounds3f { p_min: self.p2, p_max: self.p2 });
+        bounds
+    }
+    fn clipped_world_bound(&self, clip_bounds: &Bounds3f) -> Bounds3f {
+        // clip the triangle polygon against each of the clip box's six
+        // axis-aligned planes in turn, then take the bounding box of
+        // whatever (possibly degenerate) polygon is left
+        let mut poly: Vec<Point3f> = vec![self.p0, self.p1, self.p2];
+        poly = Triangle::clip_half_space(&poly, 0, clip_bounds.p_min.x, false);
+        poly = Triangle::clip_half_space(&poly, 0, clip_bounds.p_max.x, true);
+        poly = Triangle::clip_half_space(&poly, 1, clip_bounds.p_min.y, false);
+        poly = Triangle::clip_half_space(&poly, 1, clip_bounds.p_max.y, true);
+        poly = Triangle::clip_half_space(&poly, 2, clip_bounds.p_min.z, false);
+        poly = Triangle::clip_half_space(&poly, 2, clip_bounds.p_max.z, true);
+        if poly.is_empty() {
+            // no overlap with the clip box at all
+            return Bounds3f {
+                p_min: clip_bounds.p_min,
+                p_max: clip_bounds.p_min,
+            };
+        }
+        let mut bounds: Bounds3f = Bounds3f {
+            p_min: poly[0],
+            p_max: poly[0],
+        };
+        for p in &poly[1..] {
+            bounds = bnd3_union_bnd3(&bounds, &Bounds3f { p_min: *p, p_max: *p });
+        }
+        bounds
+    }
+    fn intersect(&self, ray: &mut Ray) -> Option<SurfaceInteraction> {
+        // Moeller-Trumbore ray/triangle intersection
+        let e1: Vector3f = self.p1 - self.p0;
+        let e2: Vector3f = self.p2 - self.p0;
+        let p_vec: Vector3f = ray.d.cross(e2);
+        let det: Float = e1.dot(p_vec);
+        if det.abs() < 1e-8 as Float {
+            return None;
+        }
+        let inv_det: Float = 1.0 as Float / det;
+        let t_vec: Vector3f = ray.o - self.p0;
+        let u: Float = t_vec.dot(p_vec) * inv_det;
+        if u < 0.0 as Float || u > 1.0 as Float {
+            return None;
+        }
+        let q_vec: Vector3f = t_vec.cross(e1);
+        let v: Float = ray.d.dot(q_vec) * inv_det;
+        if v < 0.0 as Float || u + v > 1.0 as Float {
+            return None;
+        }
+        let t: Float = e2.dot(q_vec) * inv_det;
+        if t <= 0.0 as Float || t >= ray.t_max.get() {
+            return None;
+        }
+        ray.t_max.set(t);
+        let p_hit: Point3f = ray.o + ray.d * t;
+        let n: Normal3f = Normal3f::from(e1.cross(e2)).normalize();
+        Some(SurfaceInteraction::new(
+            p_hit,
+            Vector3f::default(),
+            Point2f { x: u, y: v },
+            -ray.d,
+            Vector3f::default(),
+            Vector3f::default(),
+            n,
+            n,
+            ray.time,
+            None,
+        ))
+    }
+    fn intersect_p(&self, ray: &Ray) -> bool {
+        let e1: Vector3f = self.p1 - self.p0;
+        let e2: Vector3f = self.p2 - self.p0;
+        let p_vec: Vector3f = ray.d.cross(e2);
+        let det: Float = e1.dot(p_vec);
+        if det.abs() < 1e-8 as Float {
+            return false;
+        }
+        let inv_det: Float = 1.0 as Float / det;
+        let t_vec: Vector3f = ray.o - self.p0;
+        let u: Float = t_vec.dot(p_vec) * inv_det;
+        if u < 0.0 as Float || u > 1.0 as Float {
+            return false;
+        }
+        let q_vec: Vector3f = t_vec.cross(e1);
+        let v: Float = ray.d.dot(q_vec) * inv_det;
+        if v < 0.0 as Float || u + v > 1.0 as Float {
+            return false;
+        }
+        let t: Float = e2.dot(q_vec) * inv_det;
+        t > 0.0 as Float && t < ray.t_max.get()
+    }
+    fn get_material(&self) -> Option<Arc<dyn Material + Send + Sync>> {
+        self.material.clone()
+    }
+    fn get_area_light(&self) -> Option<Arc<dyn AreaLight + Send + Sync>> {
+        self.area_light.clone()
+    }
+}